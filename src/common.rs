@@ -1,8 +1,68 @@
-use rand::Rng;
-
 pub type Color = [f32; 4];
 pub type Position = [i32; 2];
 pub const CELL_WIDTH: f64 = 16.0;
+pub const GRID_SIZE: [i32; 2] = [32, 32];
+
+pub fn is_outside_grid(position: &Position) -> bool {
+    position[0] < 0 || position[0] >= GRID_SIZE[0] || position[1] < 0 || position[1] >= GRID_SIZE[1]
+}
+
+/// A dense `GRID_SIZE`-shaped grid of values, indexed by `Position`.
+#[derive(Debug, Clone)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn new(initial: T) -> Self {
+        Self {
+            cells: vec![initial; (GRID_SIZE[0] * GRID_SIZE[1]) as usize],
+        }
+    }
+
+    fn index(position: Position) -> usize {
+        (position[1] * GRID_SIZE[0] + position[0]) as usize
+    }
+
+    pub fn get(&self, position: Position) -> T {
+        self.cells[Self::index(position)]
+    }
+
+    pub fn set(&mut self, position: Position, value: T) {
+        self.cells[Self::index(position)] = value;
+    }
+}
+
+/// A small seedable xorshift PRNG. Every source of randomness in the game
+/// flows through an instance of this, so a run can be reproduced exactly
+/// by reusing its seed.
+#[derive(Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { 1 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Returns a value in `[low, high)`.
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        let span = (high - low) as u64;
+        low + (self.next_u64() % span) as i32
+    }
+}
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum Direction {
@@ -18,14 +78,19 @@ impl Default for Direction {
     }
 }
 
-pub fn random_direction() -> Direction {
-    let mut rng = rand::thread_rng();
+pub fn random_direction(rng: &mut Rng) -> Direction {
     [
         Direction::Right,
         Direction::Left,
         Direction::Up,
         Direction::Down,
-    ][rng.gen_range(0, 4)]
+    ][rng.gen_range(0, 4) as usize]
+}
+
+pub fn random_position(rng: &mut Rng) -> Position {
+    let x = rng.gen_range(0, GRID_SIZE[0]);
+    let y = rng.gen_range(0, GRID_SIZE[1]);
+    [x, y]
 }
 
 impl Direction {
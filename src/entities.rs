@@ -1,8 +1,10 @@
 use common;
 use common::Direction;
-use common::{Color, Position, CELL_WIDTH};
+use common::{Color, Grid, Position, CELL_WIDTH};
 use graphics::types::Matrix2d;
 use opengl_graphics::GlGraphics;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::Debug;
 
 const COLOR_SNAKE: Color = [1.0, 1.0, 0.0, 1.0];
@@ -14,19 +16,43 @@ const COLOR_ENEMY: Color = [0.4, 0.2, 0.3, 0.8];
 const SNAKE_MOVEMENT_COOLDOWN: f64 = 0.1;
 const BULLET_MOVEMENT_COOLDOWN: f64 = 0.07;
 const ENEMY_MOVEMENT_COOLDOWN: f64 = 0.3;
+// How strongly the hunting enemy avoids high-danger cells: each step's cost
+// is `1.0 + danger * DANGER_AVOIDANCE_WEIGHT`.
+const DANGER_AVOIDANCE_WEIGHT: f64 = 0.5;
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Right,
+    Direction::Left,
+    Direction::Up,
+    Direction::Down,
+];
+
+/// A read-only snapshot of the parts of the world a `Movement` needs in
+/// order to decide where to go: who it's chasing, which cells it must not
+/// step on, and how dangerous nearby cells are.
+pub struct WorldSnapshot<'a> {
+    pub target: Position,
+    pub blocked: &'a HashSet<Position>,
+    pub danger: &'a Grid<f64>,
+}
 
 trait Movement: Debug {
-    fn apply(&mut self, elapsed_seconds: f64) -> Option<[i32; 2]>;
+    fn apply(
+        &mut self,
+        elapsed_seconds: f64,
+        position: Position,
+        world: &WorldSnapshot,
+        rng: &mut common::Rng,
+    ) -> Option<[i32; 2]>;
 }
 
 #[derive(Debug)]
-pub struct RandomMovement {
+pub struct StaticMovement {
     timer: f64,
     direction: Direction,
     cooldown: f64,
 }
 
-impl RandomMovement {
+impl StaticMovement {
     fn new(direction: Direction, cooldown: f64) -> Self {
         Self {
             timer: 0.0,
@@ -36,12 +62,17 @@ impl RandomMovement {
     }
 }
 
-impl Movement for RandomMovement {
-    fn apply(&mut self, elapsed_seconds: f64) -> Option<[i32; 2]> {
+impl Movement for StaticMovement {
+    fn apply(
+        &mut self,
+        elapsed_seconds: f64,
+        _position: Position,
+        _world: &WorldSnapshot,
+        _rng: &mut common::Rng,
+    ) -> Option<[i32; 2]> {
         self.timer -= elapsed_seconds;
         if self.timer < 0.0 {
             self.timer += self.cooldown;
-            self.direction = common::random_direction();
             Some(self.direction.as_tuple())
         } else {
             None
@@ -49,28 +80,40 @@ impl Movement for RandomMovement {
     }
 }
 
+/// Hunts down `world.target` by recomputing the cheapest (danger-weighted)
+/// path every `cooldown` seconds and stepping along it. Falls back to a random
+/// direction whenever no path currently exists (e.g. the target is
+/// walled off by traps and snake body).
 #[derive(Debug)]
-pub struct StaticMovement {
+pub struct SeekingMovement {
     timer: f64,
     direction: Direction,
     cooldown: f64,
 }
 
-impl StaticMovement {
-    fn new(direction: Direction, cooldown: f64) -> Self {
+impl SeekingMovement {
+    fn new(cooldown: f64) -> Self {
         Self {
             timer: 0.0,
-            direction,
+            direction: Direction::default(),
             cooldown,
         }
     }
 }
 
-impl Movement for StaticMovement {
-    fn apply(&mut self, elapsed_seconds: f64) -> Option<[i32; 2]> {
+impl Movement for SeekingMovement {
+    fn apply(
+        &mut self,
+        elapsed_seconds: f64,
+        position: Position,
+        world: &WorldSnapshot,
+        rng: &mut common::Rng,
+    ) -> Option<[i32; 2]> {
         self.timer -= elapsed_seconds;
         if self.timer < 0.0 {
             self.timer += self.cooldown;
+            self.direction = find_path_direction(position, world.target, world.blocked, world.danger)
+                .unwrap_or_else(|| common::random_direction(rng));
             Some(self.direction.as_tuple())
         } else {
             None
@@ -78,6 +121,114 @@ impl Movement for StaticMovement {
     }
 }
 
+/// One entry in the A* open set, ordered so that `BinaryHeap` (a max-heap)
+/// pops the lowest `f = g + h` first. `f`/`g` are plain floats because edge
+/// costs are biased by the (fractional) danger field.
+#[derive(Copy, Clone)]
+struct OpenEntry {
+    f: f64,
+    g: f64,
+    position: Position,
+}
+
+impl PartialEq for OpenEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+
+impl Eq for OpenEntry {}
+
+impl Ord for OpenEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.partial_cmp(&self.f).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for OpenEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn manhattan_distance(a: Position, b: Position) -> f64 {
+    ((a[0] - b[0]).abs() + (a[1] - b[1]).abs()) as f64
+}
+
+/// Grid A* from `start` to `target`, avoiding `blocked` cells and the edges
+/// of the grid, biasing the cost of stepping into a cell by how dangerous
+/// `danger` says it is. Returns the direction of the first step on the
+/// cheapest path, or `None` if `target` is unreachable.
+fn find_path_direction(
+    start: Position,
+    target: Position,
+    blocked: &HashSet<Position>,
+    danger: &Grid<f64>,
+) -> Option<Direction> {
+    if start == target {
+        return None;
+    }
+
+    let mut open_set = BinaryHeap::new();
+    let mut g_score: HashMap<Position, f64> = HashMap::new();
+    let mut came_from: HashMap<Position, Position> = HashMap::new();
+
+    g_score.insert(start, 0.0);
+    open_set.push(OpenEntry {
+        f: manhattan_distance(start, target),
+        g: 0.0,
+        position: start,
+    });
+
+    while let Some(OpenEntry { g, position: current, .. }) = open_set.pop() {
+        if current == target {
+            return first_step_direction(&came_from, start, current);
+        }
+        if g > *g_score.get(&current).unwrap_or(&std::f64::INFINITY) {
+            continue;
+        }
+        for direction in &DIRECTIONS {
+            let [dx, dy] = direction.as_tuple();
+            let neighbor = [current[0] + dx, current[1] + dy];
+            if common::is_outside_grid(&neighbor) || blocked.contains(&neighbor) {
+                continue;
+            }
+            let step_cost = 1.0 + danger.get(neighbor) * DANGER_AVOIDANCE_WEIGHT;
+            let tentative_g = g + step_cost;
+            if tentative_g < *g_score.get(&neighbor).unwrap_or(&std::f64::INFINITY) {
+                g_score.insert(neighbor, tentative_g);
+                came_from.insert(neighbor, current);
+                open_set.push(OpenEntry {
+                    f: tentative_g + manhattan_distance(neighbor, target),
+                    g: tentative_g,
+                    position: neighbor,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn first_step_direction(
+    came_from: &HashMap<Position, Position>,
+    start: Position,
+    mut current: Position,
+) -> Option<Direction> {
+    let mut previous = *came_from.get(&current)?;
+    while previous != start {
+        current = previous;
+        previous = *came_from.get(&current)?;
+    }
+    direction_between(previous, current)
+}
+
+fn direction_between(from: Position, to: Position) -> Option<Direction> {
+    DIRECTIONS
+        .iter()
+        .find(|direction| direction.as_tuple() == [to[0] - from[0], to[1] - from[1]])
+        .copied()
+}
+
 #[derive(Debug)]
 pub struct Entity {
     pub position: Position,
@@ -113,20 +264,17 @@ impl Entity {
         }
     }
 
-    pub fn new_enemy(position: Position, direction: Direction) -> Self {
+    pub fn new_hunting_enemy(position: Position) -> Self {
         Self {
             position,
-            movement: Some(Box::new(RandomMovement::new(
-                direction,
-                ENEMY_MOVEMENT_COOLDOWN,
-            ))),
+            movement: Some(Box::new(SeekingMovement::new(ENEMY_MOVEMENT_COOLDOWN))),
             color: COLOR_ENEMY,
         }
     }
 
-    pub fn update(&mut self, elapsed_seconds: f64) {
+    pub fn update(&mut self, elapsed_seconds: f64, world: &WorldSnapshot, rng: &mut common::Rng) {
         if let Some(movement) = self.movement.as_mut() {
-            if let Some([dx, dy]) = movement.apply(elapsed_seconds) {
+            if let Some([dx, dy]) = movement.apply(elapsed_seconds, self.position, world, rng) {
                 self.position = [self.position[0] + dx, self.position[1] + dy];
             }
         }
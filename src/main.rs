@@ -7,12 +7,12 @@ extern crate rand;
 pub mod common;
 pub mod entities;
 
-use common::{Color, Direction, Position, CELL_WIDTH};
-use entities::{Entity, Snake};
+use common::{Color, Direction, Position, CELL_WIDTH, GRID_SIZE};
+use entities::{Entity, Snake, WorldSnapshot};
 use glutin_window::GlutinWindow as Window;
 use graphics::types::Matrix2d;
-use graphics::Transformed;
-use opengl_graphics::{GlGraphics, OpenGL};
+use graphics::{DrawState, Transformed};
+use opengl_graphics::{GlGraphics, GlyphCache, OpenGL, TextureSettings};
 use piston::event_loop::{EventSettings, Events};
 use piston::input::{RenderArgs, RenderEvent, UpdateArgs, UpdateEvent};
 use piston::window::WindowSettings;
@@ -20,18 +20,41 @@ use piston::Button::Keyboard;
 use piston::ButtonEvent;
 use piston::ButtonState;
 use piston::Key;
-use rand::Rng;
+use rand::Rng as _;
+use std::collections::HashSet;
+use std::fs;
 
 const WINDOW_SIZE: [u32; 2] = [600, 600];
 const MAX_AMMO: u32 = 5;
-const GRID_SIZE: [i32; 2] = [32, 32];
+const FONT_PATH: &str = "assets/FiraSans-Regular.ttf";
+// Smaller than any movement cooldown, so a tick never lets a moving entity
+// skip over a cell it should have collided in.
+const FIXED_DT: f64 = 1.0 / 120.0;
+// Applied as `DANGER_DECAY_PER_SECOND.powf(elapsed_seconds)` rather than as a
+// flat per-tick factor, so the decay rate doesn't depend on how many fixed
+// ticks happen per second.
+const DANGER_DECAY_PER_SECOND: f64 = 0.9;
+const DANGER_TRAP_DEPOSIT: f64 = 5.0;
+const DANGER_SNAKE_DEPOSIT: f64 = 3.0;
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Right,
+    Direction::Left,
+    Direction::Up,
+    Direction::Down,
+];
 const COLOR_BG: Color = [0.1, 0.1, 0.1, 1.0];
 const COLOR_GRID: Color = [0.3, 0.0, 0.7, 1.0];
+const COLOR_TEXT: Color = [1.0, 1.0, 1.0, 1.0];
+const COLOR_GAME_OVER_TEXT: Color = [1.0, 0.2, 0.2, 1.0];
 const PIXEL_OFFSET: [f64; 2] = [
     (WINDOW_SIZE[0] as f64 - GRID_SIZE[0] as f64 * CELL_WIDTH) / 2.0,
     (WINDOW_SIZE[1] as f64 - GRID_SIZE[1] as f64 * CELL_WIDTH) / 2.0,
 ];
 
+fn manhattan_distance(a: Position, b: Position) -> i32 {
+    (a[0] - b[0]).abs() + (a[1] - b[1]).abs()
+}
+
 #[derive(Default)]
 struct TrapSpawner {
     timer: f64,
@@ -39,82 +62,210 @@ struct TrapSpawner {
 }
 
 impl TrapSpawner {
-    fn update(&mut self, elapsed_seconds: f64) -> Option<Position> {
+    fn update(&mut self, elapsed_seconds: f64, rng: &mut common::Rng) -> Option<Position> {
         self.timer -= elapsed_seconds;
         if self.timer < 0.0 {
             self.timer += self.cooldown;
-            Some(Game::random_position())
+            Some(common::random_position(rng))
         } else {
             None
         }
     }
 }
 
+/// A key press paired with the playthrough timestamp it happened at, so a
+/// recorded run can be fed back in at the exact same moments.
+#[derive(Clone, Copy, Debug)]
+struct RecordedKeyPress {
+    timestamp: f64,
+    key: Key,
+}
+
+fn key_name(key: Key) -> Option<&'static str> {
+    match key {
+        Key::Up => Some("Up"),
+        Key::Down => Some("Down"),
+        Key::Left => Some("Left"),
+        Key::Right => Some("Right"),
+        Key::Space => Some("Space"),
+        Key::Return => Some("Return"),
+        Key::W => Some("W"),
+        Key::A => Some("A"),
+        Key::S => Some("S"),
+        Key::D => Some("D"),
+        Key::Tab => Some("Tab"),
+        _ => None,
+    }
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    match name {
+        "Up" => Some(Key::Up),
+        "Down" => Some(Key::Down),
+        "Left" => Some(Key::Left),
+        "Right" => Some(Key::Right),
+        "Space" => Some(Key::Space),
+        "Return" => Some(Key::Return),
+        "W" => Some(Key::W),
+        "A" => Some(Key::A),
+        "S" => Some(Key::S),
+        "D" => Some(Key::D),
+        "Tab" => Some(Key::Tab),
+        _ => None,
+    }
+}
+
+/// How a finished two-player game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Player1Wins,
+    Player2Wins,
+    Draw,
+}
+
+/// Reads back a recording written by `Game::write_recording`: a `SEED <n>`
+/// header line followed by one `<timestamp> <key>` line per key press. The
+/// seed is what makes replaying the key presses reproduce the original run,
+/// since it drives the exact same food/trap/enemy randomness.
+fn load_recording(path: &str) -> (u64, Vec<RecordedKeyPress>) {
+    let contents = fs::read_to_string(path).expect("Failed to read recording file");
+    let mut lines = contents.lines();
+    let seed = lines
+        .next()
+        .and_then(|line| line.strip_prefix("SEED "))
+        .and_then(|value| value.parse().ok())
+        .expect("Recording file missing SEED header");
+    let recording = lines
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let timestamp: f64 = parts.next()?.parse().ok()?;
+            let key = key_from_name(parts.next()?)?;
+            Some(RecordedKeyPress { timestamp, key })
+        })
+        .collect();
+    (seed, recording)
+}
+
 pub struct Game {
     gl: GlGraphics,
     playing: bool,
+    two_player: bool,
     snake: Snake,
+    snake2: Option<Snake>,
     food: Entity,
     bullet: Option<Entity>,
+    bullet2: Option<Entity>,
     traps: Vec<Entity>,
     trap_spawner: TrapSpawner,
     enemy: Option<Entity>,
     total_elapsed_seconds: f64,
+    seed: u64,
+    rng: common::Rng,
+    recording: Vec<RecordedKeyPress>,
+    replay: Option<(Vec<RecordedKeyPress>, usize)>,
+    score: u32,
+    accumulator: f64,
+    danger: common::Grid<f64>,
+    winner: Option<Outcome>,
 }
 
 impl Game {
-    fn new(gl: GlGraphics) -> Self {
+    fn new(gl: GlGraphics, seed: u64, two_player: bool, replay: Option<Vec<RecordedKeyPress>>) -> Self {
         Game {
             gl,
             playing: true,
+            two_player,
             snake: Default::default(),
+            snake2: None,
             food: Default::default(),
             bullet: None,
+            bullet2: None,
             traps: vec![],
             trap_spawner: TrapSpawner::default(),
             enemy: None,
             total_elapsed_seconds: 0.0,
+            seed,
+            rng: common::Rng::new(seed),
+            recording: vec![],
+            replay: replay.map(|recording| (recording, 0)),
+            score: 0,
+            accumulator: 0.0,
+            danger: common::Grid::new(0.0),
+            winner: None,
         }
     }
 
     fn set_start_state(&mut self) {
         self.playing = true;
-        self.snake = Snake::new([0, GRID_SIZE[1] / 2], MAX_AMMO);
-        self.food = Entity::new_food(Game::random_position());
+        if let Some((_, next_index)) = self.replay.as_mut() {
+            *next_index = 0;
+        }
+        if self.two_player {
+            self.snake = Snake::new([0, GRID_SIZE[1] / 2 - 4], MAX_AMMO);
+            self.snake2 = Some(Snake::new([0, GRID_SIZE[1] / 2 + 4], MAX_AMMO));
+        } else {
+            self.snake = Snake::new([0, GRID_SIZE[1] / 2], MAX_AMMO);
+            self.snake2 = None;
+        }
+        self.food = Entity::new_food(common::random_position(&mut self.rng));
         self.bullet = None;
+        self.bullet2 = None;
         self.traps = vec![];
-        self.enemy = Some(Entity::new_enemy(
-            [GRID_SIZE[0] / 2, GRID_SIZE[1] / 2],
-            Direction::Down,
-        ));
+        self.enemy = Some(Entity::new_hunting_enemy([
+            GRID_SIZE[0] / 2,
+            GRID_SIZE[1] / 2,
+        ]));
         self.trap_spawner = TrapSpawner {
             timer: 0.0,
             cooldown: 5.0,
         };
         self.total_elapsed_seconds = 0.0;
+        self.recording = vec![];
+        self.score = 0;
+        self.accumulator = 0.0;
+        self.danger = common::Grid::new(0.0);
+        self.winner = None;
     }
 
-    fn render(&mut self, args: &RenderArgs) {
+    fn render(&mut self, args: &RenderArgs, glyphs: &mut Option<GlyphCache<'_>>) {
         let snake = &self.snake;
+        let snake2 = &self.snake2.as_ref();
         let playing = self.playing;
         let food = &self.food;
         let bullet = &self.bullet.as_ref();
+        let bullet2 = &self.bullet2.as_ref();
         let traps = &self.traps;
         let enemy = &self.enemy.as_ref();
         let ammo = self.snake.ammo;
+        let score = self.score;
+        let total_elapsed_seconds = self.total_elapsed_seconds;
+        let winner = self.winner;
 
         self.gl.draw(args.viewport(), |c, gl| {
             graphics::clear(COLOR_BG, gl);
             let transform = c.transform.trans(PIXEL_OFFSET[0], PIXEL_OFFSET[1]);
             Game::render_grid(transform, gl);
             snake.render(playing, gl, transform);
+            snake2.map(|snake2| snake2.render(playing, gl, transform));
             food.render(gl, transform);
             bullet.map(|bullet| bullet.render(gl, transform));
+            bullet2.map(|bullet2| bullet2.render(gl, transform));
             for trap in traps {
                 trap.render(gl, transform);
             }
             enemy.map(|enemy| enemy.render(gl, transform));
-            Game::render_ammo_ui(ammo, gl, transform)
+            Game::render_ammo_ui(ammo, gl, transform);
+            if let Some(glyphs) = glyphs.as_mut() {
+                Game::render_hud(
+                    score,
+                    total_elapsed_seconds,
+                    playing,
+                    winner,
+                    transform,
+                    gl,
+                    glyphs,
+                );
+            }
         });
     }
 
@@ -140,6 +291,55 @@ impl Game {
         }
     }
 
+    fn render_hud(
+        score: u32,
+        total_elapsed_seconds: f64,
+        playing: bool,
+        winner: Option<Outcome>,
+        transform: Matrix2d,
+        gl: &mut GlGraphics,
+        glyphs: &mut GlyphCache<'_>,
+    ) {
+        graphics::text::Text::new_color(COLOR_TEXT, 12)
+            .draw(
+                &format!("SCORE {}", score),
+                glyphs,
+                &DrawState::default(),
+                transform.trans(2.0, 12.0),
+                gl,
+            )
+            .expect("Failed to draw score text");
+        graphics::text::Text::new_color(COLOR_TEXT, 12)
+            .draw(
+                &format!("TIME {:.0}", total_elapsed_seconds),
+                glyphs,
+                &DrawState::default(),
+                transform.trans(GRID_SIZE[0] as f64 * CELL_WIDTH - 50.0, 12.0),
+                gl,
+            )
+            .expect("Failed to draw timer text");
+        if !playing {
+            let message = match winner {
+                Some(Outcome::Player1Wins) => "PLAYER 1 WINS - press ENTER",
+                Some(Outcome::Player2Wins) => "PLAYER 2 WINS - press ENTER",
+                Some(Outcome::Draw) => "DRAW - press ENTER",
+                None => "GAME OVER - press ENTER",
+            };
+            graphics::text::Text::new_color(COLOR_GAME_OVER_TEXT, 18)
+                .draw(
+                    message,
+                    glyphs,
+                    &DrawState::default(),
+                    transform.trans(
+                        GRID_SIZE[0] as f64 * CELL_WIDTH / 2.0 - 115.0,
+                        GRID_SIZE[1] as f64 * CELL_WIDTH / 2.0,
+                    ),
+                    gl,
+                )
+                .expect("Failed to draw game over text");
+        }
+    }
+
     fn render_grid(transform: Matrix2d, gl: &mut GlGraphics) -> () {
         for y in 0..GRID_SIZE[1] + 1 {
             let y: f64 = y as f64 * CELL_WIDTH;
@@ -163,81 +363,298 @@ impl Game {
         }
     }
 
+    /// Advances the simulation by the render frame's `dt`, but only in
+    /// whole `FIXED_DT` slices, so every collision check runs at the same
+    /// fixed rate no matter how fast or slow frames are arriving.
     fn update(&mut self, args: &UpdateArgs) {
-        if self.playing {
-            let elapsed_seconds = args.dt;
-            let timestamp_1 = 30.0;
-            let timestamp_2 = 60.0;
-            if self.total_elapsed_seconds < timestamp_1
-                && self.total_elapsed_seconds + elapsed_seconds >= timestamp_1
+        if !self.playing {
+            return;
+        }
+        self.accumulator += args.dt;
+        while self.playing && self.accumulator >= FIXED_DT {
+            self.accumulator -= FIXED_DT;
+            self.tick(FIXED_DT);
+            self.apply_due_replay_presses();
+        }
+    }
+
+    /// Feeds in any recorded key presses whose timestamp has now been
+    /// reached, one fixed tick at a time, so replayed input lands on the
+    /// same tick it was recorded on rather than being batched onto
+    /// whichever render frame happens to poll for it.
+    fn apply_due_replay_presses(&mut self) {
+        loop {
+            let due_key = match self.replay.as_ref() {
+                Some((recording, next_index)) => recording
+                    .get(*next_index)
+                    .filter(|press| press.timestamp <= self.total_elapsed_seconds)
+                    .map(|press| press.key),
+                None => None,
+            };
+            match due_key {
+                Some(key) => {
+                    if let Some((_, next_index)) = self.replay.as_mut() {
+                        *next_index += 1;
+                    }
+                    self.handle_key_press(key);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn tick(&mut self, elapsed_seconds: f64) {
+        let timestamp_1 = 30.0;
+        let timestamp_2 = 60.0;
+        if self.total_elapsed_seconds < timestamp_1
+            && self.total_elapsed_seconds + elapsed_seconds >= timestamp_1
+        {
+            self.trap_spawner.cooldown = 2.0;
+        }
+        if self.total_elapsed_seconds < timestamp_2
+            && self.total_elapsed_seconds + elapsed_seconds >= timestamp_2
+        {
+            self.trap_spawner.cooldown = 0.5;
+        }
+        self.total_elapsed_seconds += elapsed_seconds;
+        self.update_danger_field(elapsed_seconds);
+        let blocked: HashSet<Position> = self
+            .traps
+            .iter()
+            .map(|trap| trap.position)
+            .chain(self.snake.positions[..self.snake.positions.len() - 1].iter().copied())
+            .chain(
+                self.snake2
+                    .iter()
+                    .flat_map(|snake2| snake2.positions[..snake2.positions.len() - 1].iter().copied()),
+            )
+            .collect();
+        let world = WorldSnapshot {
+            target: self.hunting_target(),
+            blocked: &blocked,
+            danger: &self.danger,
+        };
+        if let Some(enemy) = self.enemy.as_mut() {
+            enemy.update(elapsed_seconds, &world, &mut self.rng);
+        }
+
+        let mut snake1_dead = false;
+        let mut snake2_dead = false;
+
+        // Both snakes finish moving (and eating) for this tick before either
+        // is checked against the other's body, so a snake can't be blamed
+        // for overlapping a tail the other snake hadn't vacated yet.
+        let moved1 = self.snake.update(elapsed_seconds);
+        if moved1 {
+            let head = self.snake.head();
+            if head == self.food.position {
+                self.food.position = common::random_position(&mut self.rng);
+                self.snake.gain_ammo(3);
+                self.score += 1;
+            } else {
+                self.snake.positions.remove(0);
+            }
+        }
+
+        let moved2 = if let Some(snake2) = self.snake2.as_mut() {
+            let moved2 = snake2.update(elapsed_seconds);
+            if moved2 {
+                let head = snake2.head();
+                if head == self.food.position {
+                    self.food.position = common::random_position(&mut self.rng);
+                    snake2.gain_ammo(3);
+                    self.score += 1;
+                } else {
+                    snake2.positions.remove(0);
+                }
+            }
+            moved2
+        } else {
+            false
+        };
+
+        if moved1 {
+            let head = self.snake.head();
+            if common::is_outside_grid(&head)
+                || self.snake.self_collision()
+                || self.traps.iter().any(|trap| trap.position == head)
+                || self
+                    .enemy
+                    .as_ref()
+                    .map(|enemy| enemy.position == head)
+                    .unwrap_or(false)
+                || self
+                    .snake2
+                    .as_ref()
+                    .map(|snake2| snake2.positions.contains(&head))
+                    .unwrap_or(false)
             {
-                self.trap_spawner.cooldown = 2.0;
+                snake1_dead = true;
             }
-            if self.total_elapsed_seconds < timestamp_2
-                && self.total_elapsed_seconds + elapsed_seconds >= timestamp_2
+        }
+
+        if moved2 {
+            let snake2 = self.snake2.as_ref().expect("moved2 implies snake2 exists");
+            let head = snake2.head();
+            if common::is_outside_grid(&head)
+                || snake2.self_collision()
+                || self.traps.iter().any(|trap| trap.position == head)
+                || self
+                    .enemy
+                    .as_ref()
+                    .map(|enemy| enemy.position == head)
+                    .unwrap_or(false)
+                || self.snake.positions.contains(&head)
             {
-                self.trap_spawner.cooldown = 0.5;
+                snake2_dead = true;
             }
-            self.total_elapsed_seconds += elapsed_seconds;
-            if let Some(enemy) = self.enemy.as_mut() {
-                enemy.update(elapsed_seconds);
+        }
+
+        if let Some(bullet) = self.bullet.as_mut() {
+            bullet.update(elapsed_seconds, &world, &mut self.rng);
+            if bullet.position == self.food.position {
+                self.food.position = common::random_position(&mut self.rng);
             }
-            if self.snake.update(elapsed_seconds) {
-                let head = self.snake.head();
-                if Game::is_outside_grid(&head)
-                    || self.snake.self_collision()
-                    || self.traps.iter().any(|trap| trap.position == head)
-                    || self
-                        .enemy
-                        .as_ref()
-                        .map(|enemy| enemy.position == head)
-                        .unwrap_or(false)
-                {
-                    self.on_game_over()
-                }
+            self.traps.retain(|trap| trap.position != bullet.position);
+            if self
+                .snake2
+                .as_ref()
+                .map(|snake2| snake2.positions.contains(&bullet.position))
+                .unwrap_or(false)
+            {
+                snake2_dead = true;
+            }
+        }
 
-                if head == self.food.position {
-                    self.food.position = Game::random_position();
-                    self.snake.gain_ammo(3);
-                } else {
-                    self.snake.positions.remove(0);
+        if let Some(bullet2) = self.bullet2.as_mut() {
+            bullet2.update(elapsed_seconds, &world, &mut self.rng);
+            if bullet2.position == self.food.position {
+                self.food.position = common::random_position(&mut self.rng);
+            }
+            self.traps.retain(|trap| trap.position != bullet2.position);
+            if self.snake.positions.contains(&bullet2.position) {
+                snake1_dead = true;
+            }
+        }
+
+        if let Some(trap_position) = self.trap_spawner.update(elapsed_seconds, &mut self.rng) {
+            self.traps.push(Entity::new_trap(trap_position));
+        }
+
+        if snake1_dead || snake2_dead {
+            self.on_game_over(snake1_dead, snake2_dead);
+        }
+    }
+
+    /// Diffuses the danger field by averaging each cell with its four
+    /// neighbors and decaying the result, then deposits fresh danger at
+    /// traps and the cells around both snakes' bodies, ant-pheromone style.
+    fn update_danger_field(&mut self, elapsed_seconds: f64) {
+        let decay = DANGER_DECAY_PER_SECOND.powf(elapsed_seconds);
+        let mut diffused = common::Grid::new(0.0);
+        for y in 0..GRID_SIZE[1] {
+            for x in 0..GRID_SIZE[0] {
+                let position = [x, y];
+                let mut sum = self.danger.get(position);
+                for direction in &DIRECTIONS {
+                    let [dx, dy] = direction.as_tuple();
+                    let neighbor = [position[0] + dx, position[1] + dy];
+                    if !common::is_outside_grid(&neighbor) {
+                        sum += self.danger.get(neighbor);
+                    }
                 }
+                diffused.set(position, decay * sum / 5.0);
             }
-            if let Some(bullet) = self.bullet.as_mut() {
-                bullet.update(elapsed_seconds);
-                if bullet.position == self.food.position {
-                    self.food.position = Game::random_position();
+        }
+
+        for trap in &self.traps {
+            diffused.set(trap.position, diffused.get(trap.position) + DANGER_TRAP_DEPOSIT);
+        }
+        Game::deposit_snake_danger(&mut diffused, &self.snake.positions);
+        if let Some(snake2) = &self.snake2 {
+            Game::deposit_snake_danger(&mut diffused, &snake2.positions);
+        }
+
+        self.danger = diffused;
+    }
+
+    fn deposit_snake_danger(diffused: &mut common::Grid<f64>, positions: &[Position]) {
+        for &segment in positions {
+            for direction in &DIRECTIONS {
+                let [dx, dy] = direction.as_tuple();
+                let neighbor = [segment[0] + dx, segment[1] + dy];
+                if !common::is_outside_grid(&neighbor) {
+                    diffused.set(neighbor, diffused.get(neighbor) + DANGER_SNAKE_DEPOSIT);
                 }
-                self.traps.retain(|trap| trap.position != bullet.position);
             }
+        }
+    }
 
-            if let Some(trap_position) = self.trap_spawner.update(elapsed_seconds) {
-                self.traps.push(Entity::new_trap(trap_position));
+    /// Which snake the hunting enemy should path toward: in single-player
+    /// it's always player 1, in two-player it's whichever snake's head is
+    /// currently closer, so the enemy threatens both players.
+    fn hunting_target(&self) -> Position {
+        match self.snake2.as_ref() {
+            Some(snake2) => {
+                let enemy_position = self
+                    .enemy
+                    .as_ref()
+                    .map(|enemy| enemy.position)
+                    .unwrap_or_else(|| self.snake.head());
+                let head1 = self.snake.head();
+                let head2 = snake2.head();
+                if manhattan_distance(enemy_position, head2) < manhattan_distance(enemy_position, head1)
+                {
+                    head2
+                } else {
+                    head1
+                }
             }
+            None => self.snake.head(),
         }
     }
 
-    fn on_game_over(&mut self) -> () {
+    fn on_game_over(&mut self, snake1_dead: bool, snake2_dead: bool) -> () {
         self.traps.clear();
         self.playing = false;
-        println!("GAME OVER")
+        self.winner = if !self.two_player {
+            None
+        } else if snake1_dead && snake2_dead {
+            Some(Outcome::Draw)
+        } else if snake1_dead {
+            Some(Outcome::Player2Wins)
+        } else {
+            Some(Outcome::Player1Wins)
+        };
+        // Don't clobber the recording that's currently being replayed.
+        if self.replay.is_none() {
+            self.write_recording("recording.txt");
+        }
     }
 
-    fn random_position() -> [i32; 2] {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen_range(0, GRID_SIZE[0]);
-        let y = rng.gen_range(0, GRID_SIZE[1]);
-        [x, y]
+    fn write_recording(&self, path: &str) {
+        let mut contents = format!("SEED {}\n", self.seed);
+        contents.extend(self.recording.iter().filter_map(|press| {
+            key_name(press.key).map(|name| format!("{} {}\n", press.timestamp, name))
+        }));
+        match fs::write(path, contents) {
+            Ok(()) => println!("Wrote input recording to {}", path),
+            Err(error) => println!("Failed to write input recording: {}", error),
+        }
     }
 
-    fn is_outside_grid(position: &Position) -> bool {
-        position[0] < 0
-            || position[0] >= GRID_SIZE[0]
-            || position[1] < 0
-            || position[1] >= GRID_SIZE[1]
+    /// Applies `f` to the second player's snake, if the game has one.
+    fn with_snake2(&mut self, f: impl FnOnce(&mut Snake)) {
+        if let Some(snake2) = self.snake2.as_mut() {
+            f(snake2);
+        }
     }
 
     fn handle_key_press(&mut self, key: Key) {
+        self.recording.push(RecordedKeyPress {
+            timestamp: self.total_elapsed_seconds,
+            key,
+        });
         if self.playing {
             match key {
                 Key::Up => self.snake.try_set_direction(Direction::Up),
@@ -251,6 +668,19 @@ impl Game {
                         println!("NO AMMO");
                     }
                 }
+                Key::W => self.with_snake2(|snake2| snake2.try_set_direction(Direction::Up)),
+                Key::S => self.with_snake2(|snake2| snake2.try_set_direction(Direction::Down)),
+                Key::A => self.with_snake2(|snake2| snake2.try_set_direction(Direction::Left)),
+                Key::D => self.with_snake2(|snake2| snake2.try_set_direction(Direction::Right)),
+                Key::Tab => {
+                    if let Some(snake2) = self.snake2.as_mut() {
+                        if let Some((bullet_position, bullet_direction)) = snake2.try_shoot() {
+                            self.bullet2 = Some(Entity::new_bullet(bullet_position, bullet_direction));
+                        } else {
+                            println!("NO AMMO");
+                        }
+                    }
+                }
                 _ => {}
             }
         } else {
@@ -278,14 +708,52 @@ fn main() {
         .build()
         .expect("Failed to set up window!");
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let two_player = args.iter().any(|arg| arg == "--two-player");
+    for arg in &args {
+        if arg.starts_with("--") && arg != "--two-player" {
+            println!("Ignoring unrecognized flag: {}", arg);
+        }
+    }
+    // A replay file makes the run fully deterministic: the seed that
+    // produced the original run, and the key presses made during it, are
+    // both read back from the recording instead of from a fresh random
+    // seed and the keyboard. Only a non-flag positional is taken as the
+    // path, so a typo'd or unknown flag doesn't get treated as one.
+    let replay_path = args.iter().find(|arg| !arg.starts_with("--"));
+    let is_replaying = replay_path.is_some();
+    let (seed, replay) = match replay_path {
+        Some(path) => {
+            let (seed, recording) = load_recording(path);
+            (seed, Some(recording))
+        }
+        None => (rand::thread_rng().gen(), None),
+    };
+    println!("Seed: {}", seed);
+
+    // The font asset isn't guaranteed to be present everywhere this binary
+    // runs, so a missing/unreadable font only turns off the HUD text instead
+    // of taking down the whole game.
+    let mut glyphs: Option<GlyphCache> = match GlyphCache::new(FONT_PATH, (), TextureSettings::new())
+    {
+        Ok(cache) => Some(cache),
+        Err(error) => {
+            println!(
+                "Failed to load font {}: {} (HUD text disabled)",
+                FONT_PATH, error
+            );
+            None
+        }
+    };
+
     // Create a new game and run it.
-    let mut game = Game::new(GlGraphics::new(opengl));
+    let mut game = Game::new(GlGraphics::new(opengl), seed, two_player, replay);
     game.set_start_state();
 
     let mut events = Events::new(EventSettings::new());
     while let Some(e) = events.next(&mut window) {
         if let Some(args) = e.render_args() {
-            game.render(&args);
+            game.render(&args, &mut glyphs);
         }
 
         if let Some(args) = e.update_args() {
@@ -293,7 +761,7 @@ fn main() {
         }
 
         if let Some(args) = e.button_args() {
-            if args.state == ButtonState::Press {
+            if args.state == ButtonState::Press && !is_replaying {
                 if let Keyboard(key) = args.button {
                     game.handle_key_press(key);
                 }